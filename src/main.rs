@@ -1,25 +1,155 @@
 use std::env;
-use std::ffi::CString;
-use std::fmt::Write;
+use std::ffi::{OsStr, OsString};
+use std::mem;
+use std::os::windows::ffi::OsStrExt;
 use std::ptr;
 
 use anyhow::{anyhow, Context, Result};
 use winapi::shared::winerror;
-use winapi::um::shellapi::{self, ShellExecuteA};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::shellapi::{self, ShellExecuteExW, ShellExecuteW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+use winapi::um::processthreadsapi::GetExitCodeProcess;
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::{INFINITE, WAIT_OBJECT_0};
 use winapi::um::winuser::SW_SHOWNORMAL;
 
+/// Convert an `OsStr` into a NUL-terminated UTF-16 buffer suitable for passing to a wide Win32
+/// API. Fails if the string contains an interior NUL, matching `CString::new`'s behavior.
+fn to_wide(s: &OsStr) -> Result<Vec<u16>> {
+    let mut wide: Vec<u16> = s.encode_wide().collect();
+    if wide.iter().any(|&c| c == 0) {
+        return Err(anyhow!("string contains NUL byte"));
+    }
+    wide.push(0);
+    Ok(wide)
+}
+
+/// Verbs accepted by `--verb`, passed through as `lpOperation`. This isn't an exhaustive list of
+/// every verb a given file type might register, just the common, generally-applicable ones.
+const VERBS: &[&str] = &["open", "runas", "edit", "print", "explore"];
+
 fn help_and_exit() -> ! {
     let msg = "\
-        usage: winstart.exe FILE [ARGUMENTS...]\n\
+        usage: winstart.exe [--verb VERB] [--no-path-translate] [--wait] FILE [ARGUMENTS...]\n\
         \n\
         FILE may be a filename, URL, or executable file.\n\
         If FILE is an executable, ARGUMENTS are joined with spaces when passed\n\
         to the the programs Windows-style command-line. Any arguments with spaces\n\
-        will be surrounded by double quotes.";
+        will be surrounded by double quotes.\n\
+        \n\
+        --verb VERB  perform VERB instead of the default action for FILE. Supported verbs:\n\
+        \u{20}  open     the default action (same as omitting --verb)\n\
+        \u{20}  runas    launch elevated, triggering a UAC prompt\n\
+        \u{20}  edit     open FILE in its registered editor\n\
+        \u{20}  print    send FILE to the default printer\n\
+        \u{20}  explore  open FILE (a folder) in Explorer\n\
+        \n\
+        --no-path-translate  don't rewrite WSL (/mnt/c/...) or MSYS (/c/...) style FILE paths to\n\
+        \u{20}  their native Windows equivalent. Has no effect on URLs.\n\
+        \n\
+        --wait  wait for the launched program to exit and propagate its exit code as winstart's\n\
+        \u{20}  own. If FILE is a document or URL with no process to wait on, exits 0.";
     println!("{}", msg);
     std::process::exit(1);
 }
 
+/// True if `s` looks like a URL or a shell namespace reference (e.g. `shell:AppsFolder`) rather
+/// than a filesystem path, judging by its scheme prefix. A single-letter prefix before `:` is
+/// treated as a Windows drive letter, not a scheme.
+fn is_url(s: &str) -> bool {
+    if s.contains("://") {
+        return true;
+    }
+    match s.find(':') {
+        Some(idx) if idx > 1 => s[..idx].chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'),
+        _ => false,
+    }
+}
+
+/// Translate a WSL or MSYS POSIX-style path to its native Windows equivalent, if `file` looks
+/// like one. Returns `None` when `file` isn't a recognized POSIX path, leaving it untouched.
+fn translate_path(file: &str) -> Option<String> {
+    if !file.starts_with('/') {
+        return None;
+    }
+
+    // WSL: /mnt/<drive>/... -> <DRIVE>:\...
+    if let Some(rest) = file.strip_prefix("/mnt/") {
+        let mut parts = rest.splitn(2, '/');
+        let drive = parts.next().unwrap_or("");
+        if drive.len() == 1 && drive.chars().next().unwrap().is_ascii_alphabetic() {
+            let tail = parts.next().unwrap_or("");
+            return Some(format!("{}:\\{}", drive.to_ascii_uppercase(), tail.replace('/', "\\")));
+        }
+    }
+
+    // MSYS: /<drive>/... -> <DRIVE>:\...  (e.g. /c/Users/me -> C:\Users\me)
+    let mut parts = file[1..].splitn(2, '/');
+    let first = parts.next().unwrap_or("");
+    if first.len() == 1 && first.chars().next().unwrap().is_ascii_alphabetic() {
+        let tail = parts.next().unwrap_or("");
+        return Some(format!("{}:\\{}", first.to_ascii_uppercase(), tail.replace('/', "\\")));
+    }
+
+    // Other MSYS roots (e.g. /home/me/...) have no reliable native Windows equivalent available
+    // from the environment: `MSYSTEM_PREFIX` names a POSIX sub-prefix like /mingw64, not the
+    // native install directory, so there's nothing safe to rewrite them to. Leave them untouched.
+    None
+}
+
+fn pathext_list() -> Vec<String> {
+    let pathext = env::var_os("PATHEXT").unwrap_or_else(|| OsString::from(".COM;.EXE;.BAT;.CMD"));
+    env::split_paths(&pathext).map(|p| p.to_string_lossy().into_owned()).collect()
+}
+
+/// True if `name` looks like a bare executable name that PATH-searching makes sense for: no path
+/// separator, and either no extension or an extension that matches a `PATHEXT` entry. Other
+/// relative filenames (e.g. `report.pdf`) are left for `ShellExecute` to resolve as before, since
+/// they're documents, not programs to search PATH for.
+fn is_bare_executable_name(name: &str) -> bool {
+    if name.contains('/') || name.contains('\\') {
+        return false;
+    }
+    match name.rfind('.') {
+        None | Some(0) => true,
+        Some(i) => pathext_list().iter().any(|ext| ext.eq_ignore_ascii_case(&name[i..])),
+    }
+}
+
+/// Resolve a bare executable name (e.g. `notepad`) to an absolute path by searching `PATH`,
+/// trying each `PATHEXT` suffix in turn. Deliberately does not consult the current directory,
+/// unlike `ShellExecute`'s own search order, to avoid running a same-named binary planted in a
+/// shared or untrusted working directory.
+fn resolve_executable(name: &str) -> Result<String> {
+    let path = env::var_os("PATH").unwrap_or_default();
+    let pathext = pathext_list();
+    let has_ext = name.rfind('.').map(|i| i > 0).unwrap_or(false);
+
+    for dir in env::split_paths(&path) {
+        // an empty or relative PATH component (trailing/doubled `;`, or `.`) would otherwise make
+        // `dir.join(name)` a relative path, silently falling back to the cwd we're deliberately
+        // excluding.
+        if !dir.is_absolute() {
+            continue;
+        }
+        if has_ext {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Ok(candidate.to_string_lossy().into_owned());
+            }
+        } else {
+            for ext in &pathext {
+                let candidate = dir.join(format!("{}{}", name, ext));
+                if candidate.is_file() {
+                    return Ok(candidate.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+
+    Err(anyhow!("could not find {:?} in PATH", name))
+}
+
 /// Error messages according to
 /// https://docs.microsoft.com/en-us/windows/win32/api/shellapi/nf-shellapi-shellexecutea
 fn check_shellexecute_status(status: u32) -> Result<()> {
@@ -60,70 +190,249 @@ fn clean_environment() {
     }
 }
 
-fn run() -> Result<()> {
-    let my_args: Vec<_> = env::args().collect();
-    let file = my_args.get(1).ok_or_else(|| anyhow!("no file specified"))?;
+fn run() -> Result<i32> {
+    let my_args: Vec<_> = env::args_os().collect();
+    let mut rest = my_args[1..].iter();
 
-    match file.as_str() {
-        "-h" | "--help" | "/?" => help_and_exit(),
-        _ => (),
+    let mut verb: Option<&OsString> = None;
+    let mut path_translate = true;
+    let mut wait = false;
+    let file = loop {
+        let arg = rest.next().ok_or_else(|| anyhow!("no file specified"))?;
+        match arg.to_str() {
+            Some("-h") | Some("--help") | Some("/?") => help_and_exit(),
+            Some("--verb") => {
+                let v = rest.next().ok_or_else(|| anyhow!("--verb requires an argument"))?;
+                match v.to_str() {
+                    Some(s) if VERBS.contains(&s) => verb = Some(v),
+                    _ => {
+                        return Err(anyhow!(
+                            "unknown verb {:?}, expected one of {:?}",
+                            v,
+                            VERBS
+                        ))
+                    }
+                }
+            }
+            Some("--no-path-translate") => path_translate = false,
+            Some("--wait") => wait = true,
+            _ => break arg,
+        }
     };
 
-    let args = if my_args.len() > 2 {
-        let mut s = String::new();
-        for (i, a) in my_args[2..].iter().enumerate() {
+    let translated = file
+        .to_str()
+        .filter(|s| path_translate && !is_url(s))
+        .and_then(translate_path);
+
+    let resolved = match &translated {
+        Some(_) => None,
+        None => file
+            .to_str()
+            .filter(|s| !is_url(s) && is_bare_executable_name(s))
+            .map(resolve_executable)
+            .transpose()?,
+    };
+
+    let args = {
+        let mut s = OsString::new();
+        for (i, a) in rest.enumerate() {
             if i != 0 {
-                s.push(' ');
+                s.push(" ");
             }
-            if a.contains(' ') {
-                write!(s, "\"{}\"", a).unwrap();
+            if a.encode_wide().any(|c| c == u16::from(b' ')) {
+                s.push("\"");
+                s.push(a);
+                s.push("\"");
             } else {
-                s.push_str(a)
+                s.push(a);
             }
         }
-        Some(s)
-    } else {
-        None
+        if s.is_empty() {
+            None
+        } else {
+            Some(s)
+        }
     };
 
-    let file_c = CString::new(file.as_bytes()).context("invalid filename (contains NULL)")?;
-    let args_c = match args {
-        Some(s) => Some(CString::new(s).context("invalid arguments (contains NULL)")?),
+    let final_file = translated.or(resolved);
+    let verb_w = verb.map(|v| to_wide(v)).transpose().context("invalid verb (contains NUL)")?;
+    let file_w = match &final_file {
+        Some(s) => to_wide(OsStr::new(s)),
+        None => to_wide(file),
+    }
+    .context("invalid filename (contains NUL)")?;
+    let args_w = match args {
+        Some(s) => Some(to_wide(&s).context("invalid arguments (contains NUL)")?),
         None => None,
     };
 
     clean_environment();
 
+    if wait {
+        // safety: pointers must not outlive the Vec<u16> buffers, don't move out or drop yet
+        let verb_p = verb_w.as_ref().map(|w| w.as_ptr()).unwrap_or(ptr::null());
+        let file_p = file_w.as_ptr();
+        let args_p = args_w.as_ref().map(|w| w.as_ptr()).unwrap_or(ptr::null());
+
+        let mut sei: SHELLEXECUTEINFOW = unsafe { mem::zeroed() };
+        sei.cbSize = mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+        sei.fMask = SEE_MASK_NOCLOSEPROCESS;
+        sei.lpVerb = verb_p;
+        sei.lpFile = file_p;
+        sei.lpParameters = args_p;
+        sei.nShow = SW_SHOWNORMAL;
+
+        let ok = unsafe { ShellExecuteExW(&mut sei) };
+
+        // no-op, but won't compile if verb_w, file_w, or args_w got moved/dropped
+        #[cfg(debug_assertions)]
+        let (_, _, _) = (&verb_w, &file_w, &args_w);
+
+        if ok == 0 {
+            check_shellexecute_status(sei.hInstApp as u32)?;
+            return Err(anyhow!("ShellExecuteEx failed"));
+        }
+
+        if sei.hProcess.is_null() {
+            // FILE was a document or URL with no process handle to wait on
+            return Ok(0);
+        }
+
+        let wait_result = unsafe { WaitForSingleObject(sei.hProcess, INFINITE) };
+        if wait_result != WAIT_OBJECT_0 {
+            unsafe { CloseHandle(sei.hProcess) };
+            return Err(anyhow!("failed to wait for the launched process"));
+        }
+
+        let mut exit_code: u32 = 0;
+        let got_code = unsafe { GetExitCodeProcess(sei.hProcess, &mut exit_code) };
+        unsafe { CloseHandle(sei.hProcess) };
+        if got_code == 0 {
+            return Err(anyhow!("failed to get the launched process's exit code"));
+        }
+
+        return Ok(exit_code as i32);
+    }
+
     let ret = unsafe {
-        // safety: pointers must not outlive CString objects, don't move out or drop yet
-        let file_p = file_c.as_ptr();
-        let args_p = args_c.as_ref().map(|cs| cs.as_ptr()).unwrap_or(ptr::null());
+        // safety: pointers must not outlive the Vec<u16> buffers, don't move out or drop yet
+        let verb_p = verb_w.as_ref().map(|w| w.as_ptr()).unwrap_or(ptr::null());
+        let file_p = file_w.as_ptr();
+        let args_p = args_w.as_ref().map(|w| w.as_ptr()).unwrap_or(ptr::null());
 
-        let ret = ShellExecuteA(
+        let ret = ShellExecuteW(
             ptr::null_mut(), // hwnd
-            ptr::null(),     // lpOperation
+            verb_p,          // lpOperation
             file_p,          // lpFile
             args_p,          // lpParameters
             ptr::null(),     // lpDirectory
             SW_SHOWNORMAL,   // nShowCmd
         );
 
-        // no-op, but won't compile if file_c or args_c got moved/dropped
+        // no-op, but won't compile if verb_w, file_w, or args_w got moved/dropped
         #[cfg(debug_assertions)]
-        let (_, _) = (file_c, args_c);
+        let (_, _, _) = (verb_w, file_w, args_w);
 
-        // ShellExecuteA return an integer typed as HINSTANCE (for compatibility, of course)
+        // ShellExecuteW returns an integer typed as HINSTANCE (for compatibility, of course)
         ret as u32
     };
 
     check_shellexecute_status(ret)?;
 
-    Ok(())
+    Ok(0)
 }
 
 fn main() {
-    if let Err(e) = run() {
-        eprintln!("Error: {:#}", e);
-        std::process::exit(1);
+    match run() {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_url_detects_schemes() {
+        assert!(is_url("https://example.com"));
+        assert!(is_url("mailto:me@example.com"));
+        assert!(is_url("shell:AppsFolder"));
+    }
+
+    #[test]
+    fn is_url_rejects_paths() {
+        assert!(!is_url("C:\\Windows\\notepad.exe"));
+        assert!(!is_url("/mnt/c/Users/me/doc.pdf"));
+        assert!(!is_url("/c/Users/me/doc.pdf"));
+        assert!(!is_url("report.pdf"));
+    }
+
+    #[test]
+    fn translate_path_wsl_drive() {
+        assert_eq!(
+            translate_path("/mnt/c/Users/me/doc.pdf"),
+            Some("C:\\Users\\me\\doc.pdf".to_string())
+        );
+    }
+
+    #[test]
+    fn translate_path_msys_drive() {
+        assert_eq!(
+            translate_path("/c/Users/me/doc.pdf"),
+            Some("C:\\Users\\me\\doc.pdf".to_string())
+        );
+    }
+
+    #[test]
+    fn translate_path_msys_drive_root_only() {
+        assert_eq!(translate_path("/c"), Some("C:\\".to_string()));
+    }
+
+    #[test]
+    fn translate_path_leaves_non_posix_paths_alone() {
+        assert_eq!(translate_path("C:\\Users\\me\\doc.pdf"), None);
+        assert_eq!(translate_path("report.pdf"), None);
+    }
+
+    #[test]
+    fn translate_path_gives_up_on_unmapped_roots() {
+        assert_eq!(translate_path("/home/me/doc.pdf"), None);
+    }
+
+    #[test]
+    fn bare_executable_name_accepts_no_extension() {
+        assert!(is_bare_executable_name("notepad"));
+    }
+
+    #[test]
+    fn bare_executable_name_accepts_pathext_extension() {
+        env::set_var("PATHEXT", ".COM;.EXE;.BAT;.CMD");
+        assert!(is_bare_executable_name("notepad.exe"));
+        assert!(is_bare_executable_name("notepad.EXE"));
+        env::remove_var("PATHEXT");
+    }
+
+    #[test]
+    fn bare_executable_name_rejects_document_extension() {
+        env::set_var("PATHEXT", ".COM;.EXE;.BAT;.CMD");
+        assert!(!is_bare_executable_name("report.pdf"));
+        env::remove_var("PATHEXT");
+    }
+
+    #[test]
+    fn bare_executable_name_rejects_paths() {
+        assert!(!is_bare_executable_name("./notepad"));
+        assert!(!is_bare_executable_name("C:\\Windows\\notepad.exe"));
+        assert!(!is_bare_executable_name("bin/tool"));
+    }
+
+    #[test]
+    fn bare_executable_name_accepts_dotfile() {
+        assert!(is_bare_executable_name(".bashrc"));
     }
 }